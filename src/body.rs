@@ -16,15 +16,22 @@
  */
 
 //! packet body defines
+use crate::Error;
 use bytes::{Buf, BufMut};
 
+mod ack;
 mod data;
+mod encrypted_data;
+mod fragment;
 mod key_exchange;
 mod p2p;
 
-pub use data::Data;
-pub use key_exchange::{KeyExchange, KEY_EXCHANGE_REPLY, KEY_EXCHANGE_REQUEST};
-pub use p2p::Binding;
+pub use ack::{Ack, AckRef};
+pub use data::{Data, DataRef};
+pub use encrypted_data::{Direction, EncryptedData, EncryptedDataRef};
+pub use fragment::{Fragment, FragmentRef};
+pub use key_exchange::{KeyExchange, KeyExchangeRef, KEY_EXCHANGE_REPLY, KEY_EXCHANGE_REQUEST};
+pub use p2p::{Binding, BindingRef};
 
 /// trait for encode and decode RingLink packet
 pub trait PacketMessage {
@@ -36,3 +43,50 @@ pub trait PacketMessage {
     where
         Self: Sized;
 }
+
+/// encode `value` as a QUIC-style variable-length integer, using the
+/// shortest of the four representations (1/2/4/8 bytes) that fits
+pub(crate) fn put_varint(buf: &mut impl BufMut, value: u64) {
+    if value <= 0x3f {
+        buf.put_u8(value as u8);
+    } else if value <= 0x3fff {
+        buf.put_u16(0x4000 | value as u16);
+    } else if value <= 0x3fff_ffff {
+        buf.put_u32(0x8000_0000 | value as u32);
+    } else {
+        buf.put_u64(0xc000_0000_0000_0000 | value);
+    }
+}
+
+/// decode a QUIC-style variable-length integer
+pub(crate) fn get_varint(buf: &mut impl Buf) -> Result<u64, Error> {
+    if buf.remaining() < 1 {
+        return Err(Error::InsufficientData);
+    }
+
+    let len = 1usize << (buf.chunk()[0] >> 6);
+    if buf.remaining() < len {
+        return Err(Error::InsufficientData);
+    }
+
+    Ok(match len {
+        1 => (buf.get_u8() & 0x3f) as u64,
+        2 => (buf.get_u16() & 0x3fff) as u64,
+        4 => (buf.get_u32() & 0x3fff_ffff) as u64,
+        8 => buf.get_u64() & 0x3fff_ffff_ffff_ffff,
+        _ => unreachable!("varint length prefix only takes the values 1, 2, 4 or 8"),
+    })
+}
+
+/// on-wire size of `value` encoded as a varint
+pub(crate) const fn varint_len(value: u64) -> usize {
+    if value <= 0x3f {
+        1
+    } else if value <= 0x3fff {
+        2
+    } else if value <= 0x3fff_ffff {
+        4
+    } else {
+        8
+    }
+}
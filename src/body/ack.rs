@@ -0,0 +1,75 @@
+use crate::{Error, PacketMessage};
+use bytes::{Buf, BufMut};
+
+/// acknowledgement of one or more previously received `packet_id`s
+///
+/// sent in response to a packet carrying [`crate::PacketFlags::ACK_REQUESTED`]
+pub struct Ack {
+    pub acked: Vec<u64>,
+}
+
+impl PacketMessage for Ack {
+    fn encode(self, mut buf: impl BufMut) {
+        buf.put_u16(self.acked.len() as u16);
+        for id in self.acked {
+            buf.put_u64(id);
+        }
+    }
+
+    fn decode(mut buf: impl Buf) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let count = (buf.remaining() >= 2)
+            .then(|| buf.get_u16())
+            .ok_or(Error::InsufficientData)?;
+
+        let mut acked = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let id = (buf.remaining() >= 8)
+                .then(|| buf.get_u64())
+                .ok_or(Error::InsufficientData)?;
+            acked.push(id);
+        }
+
+        Ok(Ack { acked })
+    }
+}
+
+impl Ack {
+    pub fn len(&self) -> usize {
+        2 + 8 * self.acked.len()
+    }
+}
+
+/// zero-copy, borrowed view of [`Ack`]
+pub struct AckRef<'a> {
+    pub acked: &'a [u8],
+}
+
+impl<'a> AckRef<'a> {
+    pub(crate) fn parse(buf: &mut &'a [u8]) -> Result<AckRef<'a>, Error> {
+        if buf.len() < 2 {
+            return Err(Error::InsufficientData);
+        }
+        let count = buf.get_u16() as usize;
+        let len = count * 8;
+
+        if buf.len() < len {
+            return Err(Error::InsufficientData);
+        }
+        let (acked, rest) = buf.split_at(len);
+        *buf = rest;
+
+        Ok(AckRef { acked })
+    }
+
+    /// iterate over the acknowledged `packet_id`s
+    pub fn iter(&self) -> impl Iterator<Item = u64> + 'a {
+        self.acked.chunks_exact(8).map(|chunk| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(chunk);
+            u64::from_be_bytes(bytes)
+        })
+    }
+}
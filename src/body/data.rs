@@ -15,6 +15,7 @@
  *
  */
 
+use crate::body::{get_varint, put_varint, varint_len};
 use crate::{Error, PacketMessage};
 use bytes::{Buf, BufMut, Bytes};
 
@@ -25,7 +26,7 @@ pub struct Data {
 
 impl PacketMessage for Data {
     fn encode(self, mut buf: impl BufMut) {
-        buf.put_u64(self.data.len() as u64);
+        put_varint(&mut buf, self.data.len() as u64);
         buf.put(self.data);
     }
 
@@ -33,6 +34,31 @@ impl PacketMessage for Data {
     where
         Self: Sized,
     {
+        let len = get_varint(&mut buf)? as usize;
+
+        let data = (buf.remaining() >= len)
+            .then(|| buf.copy_to_bytes(len))
+            .ok_or(Error::InsufficientData)?;
+
+        Ok(Data { data })
+    }
+}
+
+impl Data {
+    pub fn len(&self) -> usize {
+        varint_len(self.data.len() as u64) + self.data.len()
+    }
+
+    /// encode using the pre-negotiation fixed-width length prefix, for
+    /// peers that have not advertised [`crate::PacketFlags::VARINT_LENGTHS`]
+    pub(crate) fn encode_fixed(self, mut buf: impl BufMut) {
+        buf.put_u64(self.data.len() as u64);
+        buf.put(self.data);
+    }
+
+    /// decode the pre-negotiation fixed-width length prefix, the
+    /// counterpart to [`Data::encode_fixed`]
+    pub(crate) fn decode_fixed(mut buf: impl Buf) -> Result<Self, Error> {
         let len = (buf.remaining() >= 8)
             .then(|| buf.get_u64() as usize)
             .ok_or(Error::InsufficientData)?;
@@ -45,8 +71,40 @@ impl PacketMessage for Data {
     }
 }
 
-impl Data {
-    pub fn len(&self) -> usize {
-        self.data.len()
+/// zero-copy, borrowed view of [`Data`]
+pub struct DataRef<'a> {
+    pub data: &'a [u8],
+}
+
+impl<'a> DataRef<'a> {
+    pub(crate) fn parse(buf: &mut &'a [u8]) -> Result<DataRef<'a>, Error> {
+        let len = get_varint(buf)? as usize;
+
+        if buf.len() < len {
+            return Err(Error::InsufficientData);
+        }
+
+        let (data, rest) = buf.split_at(len);
+        *buf = rest;
+
+        Ok(DataRef { data })
+    }
+
+    /// parse the pre-negotiation fixed-width length prefix, the
+    /// zero-copy counterpart to [`Data::decode_fixed`]
+    pub(crate) fn parse_fixed(buf: &mut &'a [u8]) -> Result<DataRef<'a>, Error> {
+        if buf.len() < 8 {
+            return Err(Error::InsufficientData);
+        }
+        let len = buf.get_u64() as usize;
+
+        if buf.len() < len {
+            return Err(Error::InsufficientData);
+        }
+
+        let (data, rest) = buf.split_at(len);
+        *buf = rest;
+
+        Ok(DataRef { data })
     }
 }
@@ -0,0 +1,173 @@
+use crate::{Error, PacketMessage};
+use bytes::{Buf, BufMut, Bytes};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::mem::size_of;
+
+/// size of the ChaCha20-Poly1305 nonce, in bytes
+const NONCE_LEN: usize = 12;
+
+/// which peer produced a given [`EncryptedData`] body
+///
+/// both ends of a session start their `packet_id` sequence at the same
+/// value, so the sequence alone cannot tell two directions apart. mixing
+/// this into the nonce (and into the session key via
+/// [`crate::body::KeyExchange::derive_session_key`]) keeps the initiator's
+/// and responder's nonce/key spaces disjoint, so the same `(key, nonce)`
+/// pair is never used to seal two different messages.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[repr(u8)]
+pub enum Direction {
+    /// the peer that sent the initial `KeyExchange`
+    Initiator = 0,
+    /// the peer that replied to the `KeyExchange`
+    Responder = 1,
+}
+
+/// AEAD-encrypted data packet body
+///
+/// the payload is sealed with ChaCha20-Poly1305 using a session key derived
+/// from the `KeyExchange` shared secret (see
+/// [`crate::body::KeyExchange::derive_session_key`]). the nonce is derived
+/// deterministically from the packet's `packet_id` and the sender's
+/// [`Direction`], so it never needs to be exchanged on the wire, but is
+/// still included; [`EncryptedData::open`] recomputes it from the
+/// `packet_id` and direction and rejects a body whose carried nonce
+/// differs.
+pub struct EncryptedData {
+    /// nonce used to seal/open this packet
+    pub nonce: [u8; NONCE_LEN],
+    /// ciphertext followed by the 16-byte Poly1305 tag
+    pub ciphertext: Bytes,
+}
+
+impl PacketMessage for EncryptedData {
+    fn encode(self, mut buf: impl BufMut) {
+        buf.put_slice(&self.nonce);
+        buf.put_u64(self.ciphertext.len() as u64);
+        buf.put(self.ciphertext);
+    }
+
+    fn decode(mut buf: impl Buf) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let mut nonce = [0u8; NONCE_LEN];
+        (buf.remaining() >= NONCE_LEN)
+            .then(|| buf.copy_to_slice(&mut nonce))
+            .ok_or(Error::InsufficientData)?;
+
+        let len = (buf.remaining() >= 8)
+            .then(|| buf.get_u64() as usize)
+            .ok_or(Error::InsufficientData)?;
+
+        let ciphertext = (buf.remaining() >= len)
+            .then(|| buf.copy_to_bytes(len))
+            .ok_or(Error::InsufficientData)?;
+
+        Ok(EncryptedData { nonce, ciphertext })
+    }
+}
+
+impl EncryptedData {
+    pub fn len(&self) -> usize {
+        NONCE_LEN + size_of::<u64>() + self.ciphertext.len()
+    }
+
+    /// derive the nonce used for `packet_id` when sealed by `direction`
+    ///
+    /// the top 3 bits of `packet_id` are the TTL (see
+    /// [`crate::PacketHeader::ttl`]) and are masked off so only the 61-bit
+    /// sequence contributes to the nonce; the leading byte carries the
+    /// sender's [`Direction`] so the initiator and responder never derive
+    /// the same nonce for the same sequence value
+    fn derive_nonce(packet_id: u64, direction: Direction) -> [u8; NONCE_LEN] {
+        let seq = packet_id & ((1u64 << 61) - 1);
+
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce[0] = direction as u8;
+        nonce[NONCE_LEN - size_of::<u64>()..].copy_from_slice(&seq.to_be_bytes());
+
+        nonce
+    }
+
+    /// seal `plaintext` with the session `key`, producing a body for
+    /// `packet_id` sent as `direction`
+    ///
+    /// `key` must be the session key derived for `direction` (see
+    /// [`crate::body::KeyExchange::derive_session_key`]), not a key shared
+    /// verbatim between both peers
+    pub fn seal(
+        key: &[u8; 32],
+        plaintext: &[u8],
+        packet_id: u64,
+        direction: Direction,
+    ) -> Result<EncryptedData, Error> {
+        let nonce = Self::derive_nonce(packet_id, direction);
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .map_err(|_| Error::Decryption)?;
+
+        Ok(EncryptedData {
+            nonce,
+            ciphertext: Bytes::from(ciphertext),
+        })
+    }
+
+    /// open this body with the session `key`, yielding the original
+    /// plaintext
+    ///
+    /// `packet_id` must be the id the packet was received with and
+    /// `direction` the direction the sender sealed it as; together they
+    /// are used to recompute the expected nonce so a body cannot be
+    /// replayed under a different packet id or direction
+    pub fn open(
+        &self,
+        key: &[u8; 32],
+        packet_id: u64,
+        direction: Direction,
+    ) -> Result<Bytes, Error> {
+        if self.nonce != Self::derive_nonce(packet_id, direction) {
+            return Err(Error::Decryption);
+        }
+
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&self.nonce), &*self.ciphertext)
+            .map_err(|_| Error::Decryption)?;
+
+        Ok(Bytes::from(plaintext))
+    }
+}
+
+/// zero-copy, borrowed view of [`EncryptedData`]
+pub struct EncryptedDataRef<'a> {
+    pub nonce: &'a [u8],
+    pub ciphertext: &'a [u8],
+}
+
+impl<'a> EncryptedDataRef<'a> {
+    pub(crate) fn parse(buf: &mut &'a [u8]) -> Result<EncryptedDataRef<'a>, Error> {
+        if buf.len() < NONCE_LEN {
+            return Err(Error::InsufficientData);
+        }
+        let (nonce, rest) = buf.split_at(NONCE_LEN);
+        *buf = rest;
+
+        if buf.len() < size_of::<u64>() {
+            return Err(Error::InsufficientData);
+        }
+        let len = buf.get_u64() as usize;
+
+        if buf.len() < len {
+            return Err(Error::InsufficientData);
+        }
+        let (ciphertext, rest) = buf.split_at(len);
+        *buf = rest;
+
+        Ok(EncryptedDataRef { nonce, ciphertext })
+    }
+}
@@ -0,0 +1,96 @@
+use crate::{Error, PacketMessage};
+use bytes::{Buf, BufMut, Bytes};
+use std::mem::size_of;
+
+/// one on-wire fragment of a larger packet
+///
+/// fragments share the `packet_id`/`from`/`to` of the original packet (see
+/// [`crate::Packet::fragment`]), carried in the normal packet header with
+/// [`crate::PacketFlags::FRAGMENTED`] set, and are reassembled by
+/// [`crate::Reassembler`]
+pub struct Fragment {
+    /// index of this fragment, zero-based
+    pub index: u16,
+    /// total number of fragments in the original packet
+    pub total: u16,
+    /// this fragment's slice of the original, fully-encoded body
+    pub chunk: Bytes,
+}
+
+impl PacketMessage for Fragment {
+    fn encode(self, mut buf: impl BufMut) {
+        buf.put_u16(self.index);
+        buf.put_u16(self.total);
+        buf.put_u32(self.chunk.len() as u32);
+        buf.put(self.chunk);
+    }
+
+    fn decode(mut buf: impl Buf) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        let index = (buf.remaining() >= 2)
+            .then(|| buf.get_u16())
+            .ok_or(Error::InsufficientData)?;
+
+        let total = (buf.remaining() >= 2)
+            .then(|| buf.get_u16())
+            .ok_or(Error::InsufficientData)?;
+
+        let len = (buf.remaining() >= 4)
+            .then(|| buf.get_u32() as usize)
+            .ok_or(Error::InsufficientData)?;
+
+        let chunk = (buf.remaining() >= len)
+            .then(|| buf.copy_to_bytes(len))
+            .ok_or(Error::InsufficientData)?;
+
+        Ok(Fragment {
+            index,
+            total,
+            chunk,
+        })
+    }
+}
+
+impl Fragment {
+    pub fn len(&self) -> usize {
+        2 * size_of::<u16>() + size_of::<u32>() + self.chunk.len()
+    }
+
+    /// on-wire size of a fragment's metadata, excluding its chunk
+    pub(crate) const fn overhead() -> usize {
+        2 * size_of::<u16>() + size_of::<u32>()
+    }
+}
+
+/// zero-copy, borrowed view of [`Fragment`]
+pub struct FragmentRef<'a> {
+    pub index: u16,
+    pub total: u16,
+    pub chunk: &'a [u8],
+}
+
+impl<'a> FragmentRef<'a> {
+    pub(crate) fn parse(buf: &mut &'a [u8]) -> Result<FragmentRef<'a>, Error> {
+        if buf.len() < Fragment::overhead() {
+            return Err(Error::InsufficientData);
+        }
+
+        let index = buf.get_u16();
+        let total = buf.get_u16();
+        let len = buf.get_u32() as usize;
+
+        if buf.len() < len {
+            return Err(Error::InsufficientData);
+        }
+        let (chunk, rest) = buf.split_at(len);
+        *buf = rest;
+
+        Ok(FragmentRef {
+            index,
+            total,
+            chunk,
+        })
+    }
+}
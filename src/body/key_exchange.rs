@@ -1,5 +1,8 @@
+use crate::body::{get_varint, put_varint, varint_len, Direction};
 use crate::{Error, PacketMessage};
 use bytes::{Buf, BufMut, Bytes};
+use hkdf::Hkdf;
+use sha2::Sha256;
 
 pub const KEY_EXCHANGE_REQUEST: u8 = 0x01;
 pub const KEY_EXCHANGE_REPLY: u8 = 0x02;
@@ -17,9 +20,9 @@ pub struct KeyExchange {
 impl PacketMessage for KeyExchange {
     fn encode(self, mut buf: impl BufMut) {
         buf.put_u8(self.typ);
-        buf.put_u32(self.public_key.len() as u32);
+        put_varint(&mut buf, self.public_key.len() as u64);
         buf.put(&*self.public_key);
-        buf.put_u32(self.signature.len() as u32);
+        put_varint(&mut buf, self.signature.len() as u64);
         buf.put(&*self.signature);
     }
 
@@ -31,6 +34,51 @@ impl PacketMessage for KeyExchange {
             .then(|| buf.get_u8())
             .ok_or(Error::InsufficientData)?;
 
+        let public_key_len = get_varint(&mut buf)? as usize;
+        let public_key = (buf.remaining() >= public_key_len)
+            .then(|| buf.copy_to_bytes(public_key_len))
+            .ok_or(Error::InsufficientData)?;
+
+        let signature_len = get_varint(&mut buf)? as usize;
+        let signature = (buf.remaining() >= signature_len)
+            .then(|| buf.copy_to_bytes(signature_len))
+            .ok_or(Error::InsufficientData)?;
+
+        Ok(KeyExchange {
+            typ,
+            public_key,
+            signature,
+        })
+    }
+}
+
+impl KeyExchange {
+    pub fn len(&self) -> usize {
+        self.public_key.len()
+            + self.signature.len()
+            + varint_len(self.public_key.len() as u64)
+            + varint_len(self.signature.len() as u64)
+            + size_of::<u8>()
+    }
+
+    /// encode using the pre-negotiation fixed-width `u32` length prefixes,
+    /// for peers that have not advertised
+    /// [`crate::PacketFlags::VARINT_LENGTHS`]
+    pub(crate) fn encode_fixed(self, mut buf: impl BufMut) {
+        buf.put_u8(self.typ);
+        buf.put_u32(self.public_key.len() as u32);
+        buf.put(&*self.public_key);
+        buf.put_u32(self.signature.len() as u32);
+        buf.put(&*self.signature);
+    }
+
+    /// decode the pre-negotiation fixed-width `u32` length prefixes, the
+    /// counterpart to [`KeyExchange::encode_fixed`]
+    pub(crate) fn decode_fixed(mut buf: impl Buf) -> Result<Self, Error> {
+        let typ = (buf.remaining() >= 1)
+            .then(|| buf.get_u8())
+            .ok_or(Error::InsufficientData)?;
+
         let public_key_len = (buf.remaining() >= 4)
             .then(|| buf.get_u32() as usize)
             .ok_or(Error::InsufficientData)?;
@@ -51,10 +99,99 @@ impl PacketMessage for KeyExchange {
             signature,
         })
     }
+
+    /// derive a 32-byte ChaCha20-Poly1305 session key from the DH shared
+    /// secret established during this key exchange, for use with
+    /// [`crate::body::EncryptedData`]
+    ///
+    /// `direction` selects which peer's traffic this key protects: both
+    /// ends call this twice, once per [`Direction`], so the initiator's
+    /// and responder's keys differ even though they share one DH secret.
+    /// a peer uses its own direction's key to seal outgoing packets and
+    /// the other direction's key to open packets it receives.
+    pub fn derive_session_key(shared_secret: &[u8], direction: Direction) -> [u8; 32] {
+        let hk = Hkdf::<Sha256>::new(None, shared_secret);
+
+        let info: &[u8] = match direction {
+            Direction::Initiator => b"ringlink-data-key-initiator",
+            Direction::Responder => b"ringlink-data-key-responder",
+        };
+
+        let mut key = [0u8; 32];
+        hk.expand(info, &mut key)
+            .expect("32 is a valid HKDF-SHA256 output length");
+
+        key
+    }
 }
 
-impl KeyExchange {
-    pub fn len(&self) -> usize {
-        self.public_key.len() + self.signature.len() + 2 * size_of::<u32>() + size_of::<u8>()
+/// zero-copy, borrowed view of [`KeyExchange`]
+pub struct KeyExchangeRef<'a> {
+    pub typ: u8,
+    pub public_key: &'a [u8],
+    pub signature: &'a [u8],
+}
+
+impl<'a> KeyExchangeRef<'a> {
+    pub(crate) fn parse(buf: &mut &'a [u8]) -> Result<KeyExchangeRef<'a>, Error> {
+        if buf.is_empty() {
+            return Err(Error::InsufficientData);
+        }
+        let typ = buf.get_u8();
+
+        let public_key_len = get_varint(buf)? as usize;
+        if buf.len() < public_key_len {
+            return Err(Error::InsufficientData);
+        }
+        let (public_key, rest) = buf.split_at(public_key_len);
+        *buf = rest;
+
+        let signature_len = get_varint(buf)? as usize;
+        if buf.len() < signature_len {
+            return Err(Error::InsufficientData);
+        }
+        let (signature, rest) = buf.split_at(signature_len);
+        *buf = rest;
+
+        Ok(KeyExchangeRef {
+            typ,
+            public_key,
+            signature,
+        })
+    }
+
+    /// parse the pre-negotiation fixed-width `u32` length prefixes, the
+    /// zero-copy counterpart to [`KeyExchange::decode_fixed`]
+    pub(crate) fn parse_fixed(buf: &mut &'a [u8]) -> Result<KeyExchangeRef<'a>, Error> {
+        if buf.is_empty() {
+            return Err(Error::InsufficientData);
+        }
+        let typ = buf.get_u8();
+
+        if buf.len() < 4 {
+            return Err(Error::InsufficientData);
+        }
+        let public_key_len = buf.get_u32() as usize;
+        if buf.len() < public_key_len {
+            return Err(Error::InsufficientData);
+        }
+        let (public_key, rest) = buf.split_at(public_key_len);
+        *buf = rest;
+
+        if buf.len() < 4 {
+            return Err(Error::InsufficientData);
+        }
+        let signature_len = buf.get_u32() as usize;
+        if buf.len() < signature_len {
+            return Err(Error::InsufficientData);
+        }
+        let (signature, rest) = buf.split_at(signature_len);
+        *buf = rest;
+
+        Ok(KeyExchangeRef {
+            typ,
+            public_key,
+            signature,
+        })
     }
 }
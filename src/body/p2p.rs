@@ -15,6 +15,7 @@
  *
  */
 
+use crate::body::{get_varint, put_varint};
 use crate::{Error, PacketMessage};
 use bytes::{Buf, BufMut, Bytes};
 use ringlink_identity::DeviceID;
@@ -32,10 +33,10 @@ impl PacketMessage for Binding {
     fn encode(self, mut buf: impl BufMut) {
         buf.put_slice(self.from.as_ref());
 
-        buf.put_u32(self.body.len() as u32);
+        put_varint(&mut buf, self.body.len() as u64);
         buf.put(self.body);
 
-        buf.put_u32(self.signature.len() as u32);
+        put_varint(&mut buf, self.signature.len() as u64);
         buf.put(self.signature);
     }
 
@@ -49,6 +50,49 @@ impl PacketMessage for Binding {
             .ok_or(Error::InsufficientData)?;
         let from = DeviceID::from_bytes(from);
 
+        let body_len = get_varint(&mut buf)? as usize;
+
+        let body = (buf.remaining() >= body_len)
+            .then(|| buf.copy_to_bytes(body_len))
+            .ok_or(Error::InsufficientData)?;
+
+        let signature_len = get_varint(&mut buf)? as usize;
+
+        let signature = (buf.remaining() >= signature_len)
+            .then(|| buf.copy_to_bytes(signature_len))
+            .ok_or(Error::InsufficientData)?;
+
+        Ok(Binding {
+            from,
+            body,
+            signature,
+        })
+    }
+}
+
+impl Binding {
+    /// encode using the pre-negotiation fixed-width `u32` length prefixes,
+    /// for peers that have not advertised
+    /// [`crate::PacketFlags::VARINT_LENGTHS`]
+    pub(crate) fn encode_fixed(self, mut buf: impl BufMut) {
+        buf.put_slice(self.from.as_ref());
+
+        buf.put_u32(self.body.len() as u32);
+        buf.put(self.body);
+
+        buf.put_u32(self.signature.len() as u32);
+        buf.put(self.signature);
+    }
+
+    /// decode the pre-negotiation fixed-width `u32` length prefixes, the
+    /// counterpart to [`Binding::encode_fixed`]
+    pub(crate) fn decode_fixed(mut buf: impl Buf) -> Result<Self, Error> {
+        let mut from = [0u8; DeviceID::LENGTH];
+        (buf.remaining() >= DeviceID::LENGTH)
+            .then(|| buf.copy_to_slice(&mut from))
+            .ok_or(Error::InsufficientData)?;
+        let from = DeviceID::from_bytes(from);
+
         let body_len = (buf.remaining() >= 4)
             .then(|| buf.get_u32() as usize)
             .ok_or(Error::InsufficientData)?;
@@ -72,3 +116,76 @@ impl PacketMessage for Binding {
         })
     }
 }
+
+/// zero-copy, borrowed view of [`Binding`]
+pub struct BindingRef<'a> {
+    pub from: &'a [u8],
+    pub body: &'a [u8],
+    pub signature: &'a [u8],
+}
+
+impl<'a> BindingRef<'a> {
+    pub(crate) fn parse(buf: &mut &'a [u8]) -> Result<BindingRef<'a>, Error> {
+        if buf.len() < DeviceID::LENGTH {
+            return Err(Error::InsufficientData);
+        }
+        let (from, rest) = buf.split_at(DeviceID::LENGTH);
+        *buf = rest;
+
+        let body_len = get_varint(buf)? as usize;
+        if buf.len() < body_len {
+            return Err(Error::InsufficientData);
+        }
+        let (body, rest) = buf.split_at(body_len);
+        *buf = rest;
+
+        let signature_len = get_varint(buf)? as usize;
+        if buf.len() < signature_len {
+            return Err(Error::InsufficientData);
+        }
+        let (signature, rest) = buf.split_at(signature_len);
+        *buf = rest;
+
+        Ok(BindingRef {
+            from,
+            body,
+            signature,
+        })
+    }
+
+    /// parse the pre-negotiation fixed-width `u32` length prefixes, the
+    /// zero-copy counterpart to [`Binding::decode_fixed`]
+    pub(crate) fn parse_fixed(buf: &mut &'a [u8]) -> Result<BindingRef<'a>, Error> {
+        if buf.len() < DeviceID::LENGTH {
+            return Err(Error::InsufficientData);
+        }
+        let (from, rest) = buf.split_at(DeviceID::LENGTH);
+        *buf = rest;
+
+        if buf.len() < 4 {
+            return Err(Error::InsufficientData);
+        }
+        let body_len = buf.get_u32() as usize;
+        if buf.len() < body_len {
+            return Err(Error::InsufficientData);
+        }
+        let (body, rest) = buf.split_at(body_len);
+        *buf = rest;
+
+        if buf.len() < 4 {
+            return Err(Error::InsufficientData);
+        }
+        let signature_len = buf.get_u32() as usize;
+        if buf.len() < signature_len {
+            return Err(Error::InsufficientData);
+        }
+        let (signature, rest) = buf.split_at(signature_len);
+        *buf = rest;
+
+        Ok(BindingRef {
+            from,
+            body,
+            signature,
+        })
+    }
+}
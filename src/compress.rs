@@ -0,0 +1,58 @@
+/*
+ * Copyright 2024 RingNet
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ */
+
+//! DEFLATE compression for bodies carrying [`crate::PacketFlags::COMPRESSED`]
+use crate::Error;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+
+/// limit on the size of a decompressed body; a body that reaches this
+/// size is rejected, so the largest one produced is one byte smaller
+///
+/// bounds the memory a single `COMPRESSED` packet can inflate to,
+/// mirroring how [`crate::Reassembler`] caps outstanding fragment buffers
+/// instead of trusting attacker-controlled size fields
+const MAX_DECOMPRESSED_LEN: u64 = 16 * 1024 * 1024;
+
+pub(crate) fn compress(data: &[u8]) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .expect("writing to an in-memory buffer cannot fail");
+    encoder
+        .finish()
+        .expect("flushing an in-memory buffer cannot fail")
+}
+
+pub(crate) fn decompress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut decoder = DeflateDecoder::new(data).take(MAX_DECOMPRESSED_LEN);
+    let mut out = Vec::new();
+    decoder
+        .read_to_end(&mut out)
+        .map_err(|_| Error::Decompression)?;
+
+    // `Read::take` silently stops at the limit instead of erroring, so a
+    // payload that decompresses to exactly the limit is indistinguishable
+    // from one that was truncated; treat hitting the cap as failure
+    if out.len() as u64 >= MAX_DECOMPRESSED_LEN {
+        return Err(Error::Decompression);
+    }
+
+    Ok(out)
+}
@@ -0,0 +1,60 @@
+/*
+ * Copyright 2024 RingNet
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ */
+
+//! small self-contained CRC-32 (IEEE 802.3) implementation used for the
+//! optional packet trailer, so the crate does not need an extra dependency
+//! for a single polynomial
+
+const POLY: u32 = 0xedb8_8320;
+
+const fn build_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut j = 0;
+
+        while j < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ POLY
+            } else {
+                crc >> 1
+            };
+            j += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+}
+
+const TABLE: [u32; 256] = build_table();
+
+/// compute the CRC-32 (IEEE 802.3) checksum of `data`
+pub(crate) fn checksum(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+
+    for &byte in data {
+        let idx = ((crc ^ byte as u32) & 0xff) as usize;
+        crc = (crc >> 8) ^ TABLE[idx];
+    }
+
+    !crc
+}
@@ -0,0 +1,64 @@
+/*
+ * Copyright 2024 RingNet
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ */
+
+//! error types returned while encoding or decoding a packet
+use thiserror::Error as ThisError;
+
+/// errors that can occur while encoding or decoding a packet
+#[derive(Debug, ThisError)]
+pub enum Error {
+    /// the buffer did not contain enough bytes to decode the expected field
+    #[error("insufficient data to decode packet")]
+    InsufficientData,
+
+    /// the packet kind byte did not match any known [`crate::PacketKind`]
+    #[error("unknown packet kind")]
+    UnknownKind,
+
+    /// the trailing CRC did not match the recomputed checksum of the frame
+    #[error("packet checksum mismatch")]
+    ChecksumMismatch,
+
+    /// AEAD sealing or opening of an encrypted body failed
+    #[error("failed to encrypt or decrypt packet body")]
+    Decryption,
+
+    /// a fragmented packet could not be reassembled
+    #[error("failed to reassemble fragmented packet")]
+    ReassemblyFailed,
+
+    /// `PacketFlags` contained a bit not recognized by this version of the
+    /// protocol, returned only in strict decode mode
+    #[error("packet carries unknown flag bits")]
+    UnknownFlags,
+
+    /// the compressed body could not be decompressed
+    #[error("failed to decompress packet body")]
+    Decompression,
+
+    /// [`crate::Packet::fragment`] would have produced more than
+    /// [`u16::MAX`] fragments, which cannot be represented in a fragment's
+    /// on-wire `index`/`total` fields
+    #[error("packet body requires more than u16::MAX fragments at this mtu")]
+    TooManyFragments,
+
+    /// a [`crate::PacketFlags::COMPRESSED`] body was passed to
+    /// [`crate::PacketRef::body`], which cannot decompress without an owned
+    /// buffer
+    #[error("cannot inspect a compressed body zero-copy")]
+    CompressedBody,
+}
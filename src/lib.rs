@@ -20,18 +20,26 @@
 //! the packet format used in RingLink platform
 //!
 use bitflags::bitflags;
-use bytes::{Buf, BufMut, BytesMut};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use std::mem::size_of;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 pub use body::PacketMessage;
 pub use error::Error;
 pub use id::NetId;
+pub use packet_ref::{BodyRef, PacketHeaderRef, PacketRef};
+pub use reassembly::Reassembler;
+pub use replay::{ReplayResult, ReplayWindow};
 use ringlink_identity::DeviceID;
 
 pub mod body;
+mod compress;
+mod crc32;
 mod error;
 mod id;
+mod packet_ref;
+mod reassembly;
+mod replay;
 
 /// default packet id sequence
 static PACKET_ID: AtomicU64 = AtomicU64::new(0);
@@ -40,15 +48,21 @@ const DEFAULT_TTL: u8 = 0b111;
 
 #[repr(u8)]
 #[non_exhaustive]
+#[derive(Copy, Clone, Eq, PartialEq)]
 pub enum PacketKind {
     /// main data packet
     Data = 0x01,
+    /// AEAD-encrypted data packet, see [`body::EncryptedData`]
+    EncryptedData = 0x02,
     /// key exchange packet
     KeyExchange = 0x10,
     /// p2p
     ///
     /// the detail of p2p message is not this package's concern
     P2P = 0x06,
+    /// acknowledges one or more previously received packets, see
+    /// [`body::Ack`] and [`PacketFlags::ACK_REQUESTED`]
+    Ack = 0x20,
 }
 
 impl TryFrom<u8> for PacketKind {
@@ -57,8 +71,10 @@ impl TryFrom<u8> for PacketKind {
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
             0x01 => Ok(PacketKind::Data),
+            0x02 => Ok(PacketKind::EncryptedData),
             0x10 => Ok(PacketKind::KeyExchange),
             0x06 => Ok(PacketKind::P2P),
+            0x20 => Ok(PacketKind::Ack),
             _ => Err(Error::UnknownKind),
         }
     }
@@ -68,15 +84,38 @@ impl TryFrom<u8> for PacketKind {
 #[non_exhaustive]
 pub enum PacketBody {
     Data(body::Data),
+    EncryptedData(body::EncryptedData),
     KeyExchange(body::KeyExchange),
     P2P(body::Binding),
+    /// one fragment of a larger packet, see [`Packet::fragment`]
+    Fragment(body::Fragment),
+    Ack(body::Ack),
 }
 
 bitflags! {
     #[derive(Copy, Clone, Eq, PartialEq)]
     pub struct PacketFlags: u32 {
-        /// reserved all bits
-        const _ = !0;
+        /// a trailing CRC-32 checksum follows the body, see
+        /// [`Packet::encode_with_crc`]/[`Packet::decode_verify`]
+        const HAS_CRC = 1 << 0;
+        /// this packet is one fragment of a larger packet, see
+        /// [`Packet::fragment`]/[`crate::Reassembler`]
+        const FRAGMENTED = 1 << 1;
+        /// length prefixes in `body::Data`, `body::KeyExchange` and
+        /// `body::Binding` use the QUIC variable-length integer encoding
+        /// instead of fixed-width fields
+        const VARINT_LENGTHS = 1 << 2;
+        /// the body is AEAD-encrypted, see [`body::EncryptedData`]
+        ///
+        /// set automatically by [`Packet::with_id`] whenever the body is
+        /// [`PacketBody::EncryptedData`]
+        const ENCRYPTED = 1 << 3;
+        /// the body is DEFLATE-compressed ahead of its kind-specific
+        /// framing, see [`Packet::compressed`]
+        const COMPRESSED = 1 << 4;
+        /// sender requests the receiver send back a [`body::Ack`]
+        /// acknowledging this `packet_id`
+        const ACK_REQUESTED = 1 << 5;
     }
 }
 
@@ -114,22 +153,44 @@ impl Packet {
     pub fn with_id(id: u64, from: DeviceID, to: DeviceID, body: PacketBody) -> Packet {
         let kind = match &body {
             PacketBody::Data(_) => PacketKind::Data,
+            PacketBody::EncryptedData(_) => PacketKind::EncryptedData,
             PacketBody::KeyExchange(_) => PacketKind::KeyExchange,
             PacketBody::P2P(_) => PacketKind::P2P,
+            PacketBody::Ack(_) => PacketKind::Ack,
+            PacketBody::Fragment(_) => unreachable!(
+                "fragment packets are produced by Packet::fragment, not Packet::with_id"
+            ),
         };
 
+        let mut flags = PacketFlags::VARINT_LENGTHS;
+        if kind == PacketKind::EncryptedData {
+            flags |= PacketFlags::ENCRYPTED;
+        }
+
         Packet {
             header: PacketHeader {
                 packet_id: id | ((DEFAULT_TTL as u64) << 61),
                 kind,
                 from,
                 to,
-                flags: PacketFlags::empty(),
+                flags,
             },
             body,
         }
     }
 
+    /// mark this packet to be sent with a DEFLATE-compressed body
+    pub fn compressed(mut self) -> Self {
+        self.header.flags |= PacketFlags::COMPRESSED;
+        self
+    }
+
+    /// request the receiver send back a [`body::Ack`] for this packet
+    pub fn ack_requested(mut self) -> Self {
+        self.header.flags |= PacketFlags::ACK_REQUESTED;
+        self
+    }
+
     /// Get packet total length, include header and body
     pub fn len(&self) -> usize {
         PacketHeader::len() + self.body.len()
@@ -142,27 +203,197 @@ impl Packet {
 
         buff
     }
-}
 
-impl PacketMessage for Packet {
-    fn encode(self, mut buf: impl BufMut) {
+    /// Encode the packet with a trailing CRC-32 checksum over the whole
+    /// frame, setting [`PacketFlags::HAS_CRC`] so the receiver knows to
+    /// verify it with [`Packet::decode_verify`]
+    pub fn encode_with_crc(mut self) -> BytesMut {
+        self.header.flags |= PacketFlags::HAS_CRC;
+
+        let mut buf = BytesMut::with_capacity(self.len() + size_of::<u32>());
+        self.encode(&mut buf);
+
+        buf
+    }
+
+    /// Decode a packet, rejecting any [`PacketFlags`] bit this version of
+    /// the protocol does not recognize instead of silently ignoring it
+    pub fn decode_strict(buf: impl Buf) -> Result<Self, Error> {
+        Self::decode_with(buf, true)
+    }
+
+    /// Decode a packet, verifying its trailing CRC-32 checksum when
+    /// [`PacketFlags::HAS_CRC`] is set
+    ///
+    /// packets without the flag are decoded as-is, with no trailer expected
+    pub fn decode_verify(buf: &[u8]) -> Result<Self, Error> {
+        let mut cursor = buf;
+        let packet = Self::decode(&mut cursor)?;
+
+        if !packet.header.flags.contains(PacketFlags::HAS_CRC) {
+            return Ok(packet);
+        }
+
+        let consumed = buf.len() - cursor.remaining();
+
+        if cursor.remaining() < size_of::<u32>() {
+            return Err(Error::InsufficientData);
+        }
+
+        let expected = crc32::checksum(&buf[..consumed]);
+        let actual = cursor.get_u32();
+
+        if expected != actual {
+            return Err(Error::ChecksumMismatch);
+        }
+
+        Ok(packet)
+    }
+
+    /// encode the header fields and body, without any trailer
+    fn encode_header_and_body(self, mut buf: impl BufMut) {
         buf.put_u64(self.header.packet_id);
         buf.put_u8(self.header.kind as u8);
         buf.put(&*self.header.from);
         buf.put(&*self.header.to);
         buf.put_u32(self.header.flags.bits());
 
+        let flags = self.header.flags;
+        if flags.contains(PacketFlags::COMPRESSED) {
+            let mut body_buf = BytesMut::new();
+            Self::encode_body(self.body, flags, &mut body_buf);
+
+            let data = compress::compress(&body_buf);
+            buf.put_u32(data.len() as u32);
+            buf.put_slice(&data);
+        } else {
+            Self::encode_body(self.body, flags, buf);
+        }
+    }
+
+    /// encode just the kind-specific body, with no compression
+    ///
+    /// `Data`/`KeyExchange`/`P2P` use the QUIC-style varint length prefixes
+    /// only when `flags` carries [`PacketFlags::VARINT_LENGTHS`], falling
+    /// back to the pre-negotiation fixed-width prefixes otherwise, so
+    /// peers that haven't upgraded can still be decoded correctly
+    fn encode_body(body: PacketBody, flags: PacketFlags, buf: impl BufMut) {
+        let varint = flags.contains(PacketFlags::VARINT_LENGTHS);
+
+        match body {
+            PacketBody::Data(body) if varint => body.encode(buf),
+            PacketBody::Data(body) => body.encode_fixed(buf),
+            PacketBody::EncryptedData(body) => body.encode(buf),
+            PacketBody::KeyExchange(body) if varint => body.encode(buf),
+            PacketBody::KeyExchange(body) => body.encode_fixed(buf),
+            PacketBody::P2P(body) if varint => body.encode(buf),
+            PacketBody::P2P(body) => body.encode_fixed(buf),
+            PacketBody::Ack(body) => body.encode(buf),
+            PacketBody::Fragment(body) => body.encode(buf),
+        }
+    }
+
+    /// split this packet into on-wire fragments no larger than `mtu`
+    ///
+    /// every fragment carries the original `packet_id`/`from`/`to` with
+    /// [`PacketFlags::FRAGMENTED`] set, so a [`Reassembler`] on the other
+    /// end can group and reorder them regardless of transport order.
+    /// [`PacketFlags::ACK_REQUESTED`] and [`PacketFlags::VARINT_LENGTHS`]
+    /// are carried through to every fragment, since the receiver needs
+    /// them to acknowledge the packet and to decode the reassembled body
+    /// correctly; `HAS_CRC`/`COMPRESSED` do not apply per-fragment and are
+    /// dropped, since each fragment's body is neither checksummed nor
+    /// compressed on its own.
+    ///
+    /// when `mtu` is smaller than the packet header plus a fragment's
+    /// metadata, each fragment carries a single body byte and its frame
+    /// is larger than `mtu`.
+    ///
+    /// fails with [`Error::TooManyFragments`] if the body needs more than
+    /// [`u16::MAX`] fragments at this `mtu`, since a fragment's on-wire
+    /// `index`/`total` cannot represent more than that without wrapping
+    /// and colliding with earlier fragments
+    pub fn fragment(self, mtu: usize) -> Result<impl Iterator<Item = BytesMut>, Error> {
+        let packet_id = self.header.packet_id;
+        let kind = self.header.kind;
+        let from = self.header.from;
+        let to = self.header.to;
+        let body_flags = self.header.flags;
+        let flags = (body_flags & (PacketFlags::ACK_REQUESTED | PacketFlags::VARINT_LENGTHS))
+            | PacketFlags::FRAGMENTED;
+        let varint = body_flags.contains(PacketFlags::VARINT_LENGTHS);
+
+        let mut body_buf = BytesMut::with_capacity(self.body.len());
         match self.body {
-            PacketBody::Data(body) => body.encode(buf),
-            PacketBody::KeyExchange(body) => body.encode(buf),
-            PacketBody::P2P(body) => body.encode(buf),
+            PacketBody::Data(body) if varint => body.encode(&mut body_buf),
+            PacketBody::Data(body) => body.encode_fixed(&mut body_buf),
+            PacketBody::EncryptedData(body) => body.encode(&mut body_buf),
+            PacketBody::KeyExchange(body) if varint => body.encode(&mut body_buf),
+            PacketBody::KeyExchange(body) => body.encode_fixed(&mut body_buf),
+            PacketBody::P2P(body) if varint => body.encode(&mut body_buf),
+            PacketBody::P2P(body) => body.encode_fixed(&mut body_buf),
+            PacketBody::Ack(body) => body.encode(&mut body_buf),
+            PacketBody::Fragment(_) => unreachable!("a fragment cannot be fragmented again"),
         }
+        let body_buf = body_buf.freeze();
+
+        let chunk_size = mtu
+            .saturating_sub(PacketHeader::len() + body::Fragment::overhead())
+            .max(1);
+        let chunks: Vec<Bytes> = body_buf
+            .chunks(chunk_size)
+            .map(Bytes::copy_from_slice)
+            .collect();
+
+        if chunks.len() > u16::MAX as usize {
+            return Err(Error::TooManyFragments);
+        }
+        let total = chunks.len() as u16;
+
+        Ok(chunks.into_iter().enumerate().map(move |(index, chunk)| {
+            let fragment = body::Fragment {
+                index: index as u16,
+                total,
+                chunk,
+            };
+
+            let mut buf = BytesMut::with_capacity(PacketHeader::len() + fragment.len());
+            buf.put_u64(packet_id);
+            buf.put_u8(kind as u8);
+            buf.put(&*from);
+            buf.put(&*to);
+            buf.put_u32(flags.bits());
+            fragment.encode(&mut buf);
+
+            buf
+        }))
     }
+}
 
-    fn decode(mut buf: impl Buf) -> Result<Self, Error>
+impl PacketMessage for Packet {
+    fn encode(self, mut buf: impl BufMut) {
+        if self.header.flags.contains(PacketFlags::HAS_CRC) {
+            let mut scratch = BytesMut::with_capacity(self.len());
+            self.encode_header_and_body(&mut scratch);
+            let crc = crc32::checksum(&scratch);
+
+            buf.put(scratch);
+            buf.put_u32(crc);
+        } else {
+            self.encode_header_and_body(buf);
+        }
+    }
+
+    fn decode(buf: impl Buf) -> Result<Self, Error>
     where
         Self: Sized,
     {
+        Self::decode_with(buf, false)
+    }
+}
+
+impl Packet {
+    fn decode_with(mut buf: impl Buf, strict: bool) -> Result<Self, Error> {
         // ensure packet have enough data
         if buf.remaining() < PacketHeader::len() {
             return Err(Error::InsufficientData);
@@ -177,13 +408,25 @@ impl PacketMessage for Packet {
         buf.copy_to_slice(&mut from);
         buf.copy_to_slice(&mut to);
 
-        let flags = buf.get_u32();
-        let flags = PacketFlags::from_bits_truncate(flags);
+        let raw_flags = buf.get_u32();
+        let flags = if strict {
+            PacketFlags::from_bits(raw_flags).ok_or(Error::UnknownFlags)?
+        } else {
+            PacketFlags::from_bits_truncate(raw_flags)
+        };
 
-        let body = match kind {
-            PacketKind::Data => PacketBody::Data(body::Data::decode(buf)?),
-            PacketKind::KeyExchange => PacketBody::KeyExchange(body::KeyExchange::decode(buf)?),
-            PacketKind::P2P => PacketBody::P2P(body::Binding::decode(buf)?),
+        let body = if flags.contains(PacketFlags::COMPRESSED) {
+            if buf.remaining() < size_of::<u32>() {
+                return Err(Error::InsufficientData);
+            }
+            let len = buf.get_u32() as usize;
+            if buf.remaining() < len {
+                return Err(Error::InsufficientData);
+            }
+            let data = compress::decompress(&buf.copy_to_bytes(len))?;
+            Self::decode_body(kind, flags, &mut data.as_slice())?
+        } else {
+            Self::decode_body(kind, flags, buf)?
         };
 
         Ok(Packet {
@@ -197,6 +440,41 @@ impl PacketMessage for Packet {
             body,
         })
     }
+
+    /// decode the kind-specific body, after any compression has been undone
+    fn decode_body(
+        kind: PacketKind,
+        flags: PacketFlags,
+        buf: impl Buf,
+    ) -> Result<PacketBody, Error> {
+        if flags.contains(PacketFlags::FRAGMENTED) {
+            return Ok(PacketBody::Fragment(body::Fragment::decode(buf)?));
+        }
+
+        let varint = flags.contains(PacketFlags::VARINT_LENGTHS);
+
+        Ok(match kind {
+            PacketKind::Data => PacketBody::Data(if varint {
+                body::Data::decode(buf)?
+            } else {
+                body::Data::decode_fixed(buf)?
+            }),
+            PacketKind::EncryptedData => {
+                PacketBody::EncryptedData(body::EncryptedData::decode(buf)?)
+            }
+            PacketKind::KeyExchange => PacketBody::KeyExchange(if varint {
+                body::KeyExchange::decode(buf)?
+            } else {
+                body::KeyExchange::decode_fixed(buf)?
+            }),
+            PacketKind::P2P => PacketBody::P2P(if varint {
+                body::Binding::decode(buf)?
+            } else {
+                body::Binding::decode_fixed(buf)?
+            }),
+            PacketKind::Ack => PacketBody::Ack(body::Ack::decode(buf)?),
+        })
+    }
 }
 
 impl PacketHeader {
@@ -216,8 +494,11 @@ impl PacketBody {
     pub fn len(&self) -> usize {
         match self {
             PacketBody::Data(data) => data.len(),
+            PacketBody::EncryptedData(data) => data.len(),
             PacketBody::KeyExchange(kex) => kex.len(),
             PacketBody::P2P(_) => 0,
+            PacketBody::Ack(ack) => ack.len(),
+            PacketBody::Fragment(fragment) => fragment.len(),
         }
     }
 }
@@ -0,0 +1,147 @@
+/*
+ * Copyright 2024 RingNet
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ */
+
+//! zero-copy, borrowed packet parsing
+//!
+//! unlike [`crate::Packet::decode`], which copies `from`/`to`/body bytes out
+//! of the buffer, [`PacketRef`] only validates field offsets and returns
+//! slices that borrow from the original buffer. this is intended for
+//! high-throughput relays that need to inspect a header (to route on `to`
+//! and decrement TTL) without paying for a copy of the payload.
+use crate::body::{AckRef, BindingRef, DataRef, EncryptedDataRef, FragmentRef, KeyExchangeRef};
+use crate::{Error, PacketFlags, PacketHeader, PacketKind};
+use bytes::Buf;
+use ringlink_identity::DeviceID;
+
+/// borrowed view of a [`crate::PacketHeader`]
+pub struct PacketHeaderRef<'a> {
+    pub packet_id: u64,
+    pub kind: PacketKind,
+    pub from: &'a [u8],
+    pub to: &'a [u8],
+    pub flags: PacketFlags,
+}
+
+impl<'a> PacketHeaderRef<'a> {
+    /// the packet's ttl, see [`crate::PacketHeader::ttl`]
+    pub const fn ttl(&self) -> u8 {
+        ((self.packet_id >> 61) & 0b111) as u8
+    }
+}
+
+/// borrowed view of a [`crate::PacketBody`]
+pub enum BodyRef<'a> {
+    Data(DataRef<'a>),
+    EncryptedData(EncryptedDataRef<'a>),
+    KeyExchange(KeyExchangeRef<'a>),
+    P2P(BindingRef<'a>),
+    Fragment(FragmentRef<'a>),
+    Ack(AckRef<'a>),
+}
+
+/// zero-copy, borrowed view of a [`crate::Packet`]
+pub struct PacketRef<'a> {
+    pub header: PacketHeaderRef<'a>,
+    body: &'a [u8],
+}
+
+impl<'a> PacketRef<'a> {
+    /// parse the header of `buf` without copying its payload
+    pub fn parse(buf: &'a [u8]) -> Result<PacketRef<'a>, Error> {
+        if buf.len() < PacketHeader::len() {
+            return Err(Error::InsufficientData);
+        }
+
+        let mut cursor = buf;
+        let packet_id = cursor.get_u64();
+        let kind = PacketKind::try_from(cursor.get_u8())?;
+
+        let (from, rest) = cursor.split_at(DeviceID::LENGTH);
+        cursor = rest;
+        let (to, rest) = cursor.split_at(DeviceID::LENGTH);
+        cursor = rest;
+
+        let flags = PacketFlags::from_bits_truncate(cursor.get_u32());
+
+        Ok(PacketRef {
+            header: PacketHeaderRef {
+                packet_id,
+                kind,
+                from,
+                to,
+                flags,
+            },
+            body: cursor,
+        })
+    }
+
+    /// the packet's kind
+    pub fn kind(&self) -> PacketKind {
+        self.header.kind
+    }
+
+    /// the packet's source device id
+    pub fn from(&self) -> &'a [u8] {
+        self.header.from
+    }
+
+    /// the packet's destination device id
+    pub fn to(&self) -> &'a [u8] {
+        self.header.to
+    }
+
+    /// parse the body, borrowing from the original buffer
+    ///
+    /// a [`PacketFlags::COMPRESSED`] body cannot be inspected zero-copy,
+    /// since decompression requires an owned buffer; use
+    /// [`crate::Packet::decode`] for those packets instead
+    pub fn body(&self) -> Result<BodyRef<'a>, Error> {
+        if self.header.flags.contains(PacketFlags::COMPRESSED) {
+            return Err(Error::CompressedBody);
+        }
+
+        let mut cursor = self.body;
+
+        if self.header.flags.contains(PacketFlags::FRAGMENTED) {
+            return Ok(BodyRef::Fragment(FragmentRef::parse(&mut cursor)?));
+        }
+
+        let varint = self.header.flags.contains(PacketFlags::VARINT_LENGTHS);
+
+        match self.header.kind {
+            PacketKind::Data => Ok(BodyRef::Data(if varint {
+                DataRef::parse(&mut cursor)?
+            } else {
+                DataRef::parse_fixed(&mut cursor)?
+            })),
+            PacketKind::EncryptedData => {
+                Ok(BodyRef::EncryptedData(EncryptedDataRef::parse(&mut cursor)?))
+            }
+            PacketKind::KeyExchange => Ok(BodyRef::KeyExchange(if varint {
+                KeyExchangeRef::parse(&mut cursor)?
+            } else {
+                KeyExchangeRef::parse_fixed(&mut cursor)?
+            })),
+            PacketKind::P2P => Ok(BodyRef::P2P(if varint {
+                BindingRef::parse(&mut cursor)?
+            } else {
+                BindingRef::parse_fixed(&mut cursor)?
+            })),
+            PacketKind::Ack => Ok(BodyRef::Ack(AckRef::parse(&mut cursor)?)),
+        }
+    }
+}
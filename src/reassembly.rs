@@ -0,0 +1,159 @@
+/*
+ * Copyright 2024 RingNet
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ */
+
+//! reassembly of fragmented packets produced by [`crate::Packet::fragment`]
+use crate::{body, Error, PacketBody, PacketFlags, PacketHeader, PacketKind, PacketMessage};
+use bytes::{Bytes, BytesMut};
+use ringlink_identity::DeviceID;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// in-flight fragments for a single `(from, packet_id)`
+struct PendingPacket {
+    total: u16,
+    kind: PacketKind,
+    to: DeviceID,
+    /// flags carried by the fragments themselves (e.g. `ACK_REQUESTED`,
+    /// `VARINT_LENGTHS`), minus `FRAGMENTED`, which no longer applies once
+    /// reassembled
+    flags: PacketFlags,
+    chunks: HashMap<u16, Bytes>,
+    last_seen: Instant,
+}
+
+/// reassembles fragmented packets received from potentially many peers
+///
+/// buffers are bounded by `max_pending` and evicted after `timeout` of
+/// inactivity, so a peer that never completes a fragment sequence cannot
+/// exhaust memory
+pub struct Reassembler {
+    pending: HashMap<(DeviceID, u64), PendingPacket>,
+    max_pending: usize,
+    timeout: Duration,
+}
+
+impl Reassembler {
+    /// create a reassembler bounding outstanding buffers to `max_pending`,
+    /// each evicted after `timeout` without a new fragment
+    pub fn new(max_pending: usize, timeout: Duration) -> Reassembler {
+        Reassembler {
+            pending: HashMap::new(),
+            max_pending,
+            timeout,
+        }
+    }
+
+    /// evict any buffer that has not received a fragment within `timeout`
+    fn evict_expired(&mut self) {
+        let timeout = self.timeout;
+        let now = Instant::now();
+        self.pending
+            .retain(|_, pending| now.duration_since(pending.last_seen) < timeout);
+    }
+
+    /// feed a fragment into the reassembler
+    ///
+    /// returns the reassembled [`crate::Packet`] once every fragment has
+    /// arrived, `None` while still waiting on more, or
+    /// [`Error::ReassemblyFailed`] if the fragment is inconsistent with
+    /// ones already buffered or the buffer table is full
+    pub fn insert(&mut self, packet: crate::Packet) -> Result<Option<crate::Packet>, Error> {
+        let fragment = match packet.body {
+            PacketBody::Fragment(fragment) => fragment,
+            _ => return Err(Error::ReassemblyFailed),
+        };
+
+        self.evict_expired();
+
+        let key = (packet.header.from.clone(), packet.header.packet_id);
+
+        if !self.pending.contains_key(&key) && self.pending.len() >= self.max_pending {
+            return Err(Error::ReassemblyFailed);
+        }
+
+        let pending = self
+            .pending
+            .entry(key.clone())
+            .or_insert_with(|| PendingPacket {
+                total: fragment.total,
+                kind: packet.header.kind,
+                to: packet.header.to,
+                flags: packet.header.flags & !PacketFlags::FRAGMENTED,
+                chunks: HashMap::new(),
+                last_seen: Instant::now(),
+            });
+
+        if pending.total != fragment.total {
+            self.pending.remove(&key);
+            return Err(Error::ReassemblyFailed);
+        }
+
+        pending.last_seen = Instant::now();
+        pending.chunks.insert(fragment.index, fragment.chunk);
+
+        if pending.chunks.len() < pending.total as usize {
+            return Ok(None);
+        }
+
+        let pending = self.pending.remove(&key).unwrap();
+
+        let mut body_buf = BytesMut::new();
+        for index in 0..pending.total {
+            let chunk = pending
+                .chunks
+                .get(&index)
+                .ok_or(Error::ReassemblyFailed)?;
+            body_buf.extend_from_slice(chunk);
+        }
+        let body_buf = body_buf.freeze();
+
+        let varint = pending.flags.contains(PacketFlags::VARINT_LENGTHS);
+
+        let body = match pending.kind {
+            PacketKind::Data => PacketBody::Data(if varint {
+                body::Data::decode(body_buf)?
+            } else {
+                body::Data::decode_fixed(body_buf)?
+            }),
+            PacketKind::EncryptedData => {
+                PacketBody::EncryptedData(body::EncryptedData::decode(body_buf)?)
+            }
+            PacketKind::KeyExchange => PacketBody::KeyExchange(if varint {
+                body::KeyExchange::decode(body_buf)?
+            } else {
+                body::KeyExchange::decode_fixed(body_buf)?
+            }),
+            PacketKind::P2P => PacketBody::P2P(if varint {
+                body::Binding::decode(body_buf)?
+            } else {
+                body::Binding::decode_fixed(body_buf)?
+            }),
+            PacketKind::Ack => PacketBody::Ack(body::Ack::decode(body_buf)?),
+        };
+
+        Ok(Some(crate::Packet {
+            header: PacketHeader {
+                packet_id: key.1,
+                kind: pending.kind,
+                from: key.0,
+                to: pending.to,
+                flags: pending.flags,
+            },
+            body,
+        }))
+    }
+}
@@ -0,0 +1,97 @@
+/*
+ * Copyright 2024 RingNet
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *
+ */
+
+//! anti-replay sliding-window validator over `packet_id`
+
+/// size of the sliding window, in packets
+const WINDOW_SIZE: u64 = 128;
+
+/// outcome of checking a `packet_id` against a [`ReplayWindow`]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ReplayResult {
+    /// the packet has not been seen before and is within the window
+    Accept,
+    /// the packet has already been seen
+    Duplicate,
+    /// the packet is older than the window can track
+    TooOld,
+}
+
+/// sliding-window replay validator for a single remote peer
+///
+/// a receiver keeps one `ReplayWindow` per remote [`crate::DeviceID`] and
+/// calls [`ReplayWindow::check`] with each incoming `packet_id`
+pub struct ReplayWindow {
+    /// highest accepted 61-bit sequence so far
+    highest: u64,
+    /// bitmap of the `WINDOW_SIZE` sequences at and below `highest`, bit 0
+    /// corresponds to `highest` itself
+    bitmap: u128,
+    /// whether any packet has been accepted yet
+    initialized: bool,
+}
+
+impl ReplayWindow {
+    /// create an empty replay window
+    pub fn new() -> ReplayWindow {
+        ReplayWindow {
+            highest: 0,
+            bitmap: 0,
+            initialized: false,
+        }
+    }
+
+    /// check and record `packet_id`, masking off its TTL bits
+    pub fn check(&mut self, packet_id: u64) -> ReplayResult {
+        let seq = packet_id & ((1u64 << 61) - 1);
+
+        if !self.initialized {
+            self.initialized = true;
+            self.highest = seq;
+            self.bitmap = 1;
+            return ReplayResult::Accept;
+        }
+
+        if seq > self.highest {
+            let shift = seq - self.highest;
+            self.bitmap = if shift >= 128 { 0 } else { self.bitmap << shift };
+            self.bitmap |= 1;
+            self.highest = seq;
+            return ReplayResult::Accept;
+        }
+
+        let age = self.highest - seq;
+
+        if age >= WINDOW_SIZE {
+            return ReplayResult::TooOld;
+        }
+
+        let bit = 1u128 << age;
+        if self.bitmap & bit != 0 {
+            return ReplayResult::Duplicate;
+        }
+
+        self.bitmap |= bit;
+        ReplayResult::Accept
+    }
+}
+
+impl Default for ReplayWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}